@@ -0,0 +1,50 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Asset registry.
+
+use exonum::crypto::{Hash, PublicKey};
+
+use super::proto;
+
+/// Identifier of an asset type, derived from the hash of the transaction that issued it.
+pub type AssetId = Hash;
+
+/// Returns the identifier of the native asset that every wallet is seeded with on creation.
+pub fn native_asset_id() -> AssetId {
+    AssetId::default()
+}
+
+/// Asset registered on the ledger.
+#[derive(Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::Asset", serde_pb_convert)]
+pub struct Asset {
+    /// Human-readable name of the asset.
+    pub name: String,
+    /// Public key of the wallet that issued the asset.
+    pub issuer: PublicKey,
+    /// Total supply issued for this asset.
+    pub supply: u64,
+}
+
+impl Asset {
+    /// Creates a new asset.
+    pub fn new(name: &str, &issuer: &PublicKey, supply: u64) -> Self {
+        Self {
+            name: name.to_owned(),
+            issuer,
+            supply,
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pending swap offer.
+
+use exonum::crypto::{Hash, PublicKey};
+
+use super::proto;
+use crate::asset::AssetId;
+
+/// Atomic two-party asset swap offer stored in the database.
+#[derive(Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::PendingSwap", serde_pb_convert)]
+pub struct PendingSwap {
+    /// Hash of the `SwapOffer` transaction that created this swap.
+    pub offer_hash: Hash,
+    /// Public key of the wallet that made the offer.
+    pub maker: PublicKey,
+    /// Public key of the wallet allowed to accept the offer.
+    pub taker: PublicKey,
+    /// Asset frozen and offered by the maker.
+    pub offered_asset: AssetId,
+    /// Amount of `offered_asset` frozen by the maker.
+    pub offered_amount: u64,
+    /// Asset requested from the taker in exchange.
+    pub wanted_asset: AssetId,
+    /// Amount of `wanted_asset` the taker must pay.
+    pub wanted_amount: u64,
+    /// Height of the blockchain after which the maker may cancel and reclaim the offer.
+    pub deadline_height: u64,
+    /// Whether the swap has already been executed by a matching `AcceptSwap`.
+    pub fulfilled: bool,
+    /// Whether the offer was cancelled and its frozen funds refunded to the maker.
+    pub cancelled: bool,
+}
+
+impl PendingSwap {
+    /// Create new PendingSwap.
+    pub fn new(
+        offer_hash: Hash,
+        &maker: &PublicKey,
+        &taker: &PublicKey,
+        offered_asset: AssetId,
+        offered_amount: u64,
+        wanted_asset: AssetId,
+        wanted_amount: u64,
+        deadline_height: u64,
+        fulfilled: bool,
+        cancelled: bool,
+    ) -> Self {
+        Self {
+            offer_hash,
+            maker,
+            taker,
+            offered_asset,
+            offered_amount,
+            wanted_asset,
+            wanted_amount,
+            deadline_height,
+            fulfilled,
+            cancelled,
+        }
+    }
+    /// Returns a copy of this pending swap with fulfilled flag set.
+    pub fn set_fulfilled(self) -> Self {
+        Self {
+            fulfilled: true,
+            ..self
+        }
+    }
+    /// Returns a copy of this pending swap with cancelled flag set.
+    pub fn set_cancelled(self) -> Self {
+        Self {
+            cancelled: true,
+            ..self
+        }
+    }
+}
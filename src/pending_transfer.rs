@@ -18,6 +18,7 @@
 use exonum::crypto::{Hash, PublicKey};
 
 use super::proto;
+use crate::asset::AssetId;
 
 /// Wallet information stored in the database.
 #[derive(Clone, Debug, ProtobufConvert)]
@@ -27,12 +28,22 @@ pub struct PendingTransfer {
     pub tx_hash: Hash,
     /// TODO
     pub from: PublicKey,
-    /// TODO    
+    /// TODO
     pub to: PublicKey,
-    /// TODO    
+    /// TODO
+    pub approver: PublicKey,
+    /// Asset being transferred.
+    pub asset_id: AssetId,
+    /// TODO
     pub amount: u64,
-    /// TODO    
+    /// Fee paid to the fee collector once the transfer is confirmed.
+    pub fee: u64,
+    /// Height of the blockchain after which the sender may cancel and reclaim the transfer.
+    pub deadline_height: u64,
+    /// TODO
     pub fulfilled: bool,
+    /// Whether the transfer was cancelled and its funds refunded to the sender.
+    pub cancelled: bool,
 }
 
 impl PendingTransfer {
@@ -41,15 +52,25 @@ impl PendingTransfer {
         tx_hash: Hash,
         &from: &PublicKey,
         &to: &PublicKey,
+        &approver: &PublicKey,
+        asset_id: AssetId,
         amount: u64,
+        fee: u64,
+        deadline_height: u64,
         fulfilled: bool,
+        cancelled: bool,
     ) -> Self {
         Self {
             tx_hash,
             from,
             to,
+            approver,
+            asset_id,
             amount,
+            fee,
+            deadline_height,
             fulfilled,
+            cancelled,
         }
     }
     /// Returns a copy of this pending transfer with fulfilled flag set.
@@ -59,4 +80,11 @@ impl PendingTransfer {
             ..self
         }
     }
+    /// Returns a copy of this pending transfer with cancelled flag set.
+    pub fn set_cancelled(self) -> Self {
+        Self {
+            cancelled: true,
+            ..self
+        }
+    }
 }
@@ -16,10 +16,16 @@
 
 use exonum::{
     crypto::{Hash, PublicKey},
-    storage::{Fork, ProofListIndex, ProofMapIndex, Snapshot},
+    storage::{Entry, Fork, ListProof, MapProof, ProofListIndex, ProofMapIndex, Snapshot},
 };
 
-use crate::{wallet::Wallet, INITIAL_BALANCE, pending_transfer::PendingTransfer};
+use crate::{
+    asset::{native_asset_id, Asset, AssetId},
+    pending_swap::PendingSwap,
+    pending_transfer::PendingTransfer,
+    wallet::Wallet,
+    INITIAL_BALANCE,
+};
 
 /// Database schema for the cryptocurrency.
 #[derive(Debug)]
@@ -62,14 +68,122 @@ where
         self.pending_transfers().get(hash)
     }
 
+    /// Returns a Merkle proof of the presence (or absence) of the pending transfer with the
+    /// given transaction hash, provable against `state_hash`.
+    pub fn pending_transfer_proof(&self, tx_hash: &Hash) -> MapProof<Hash, PendingTransfer> {
+        self.pending_transfers().get_proof(*tx_hash)
+    }
+
+    /// Returns a Merkle proof of the wallet history entries in the given index range,
+    /// provable against the corresponding record in `wallets`.
+    pub fn wallet_history_proof(
+        &self,
+        public_key: &PublicKey,
+        from: u64,
+        to: u64,
+    ) -> ListProof<Hash> {
+        self.wallet_history(public_key).get_range_proof(from..to)
+    }
+
+    /// Returns the `Entry` holding the public key of the wallet that collects transfer fees.
+    pub fn fee_collector_entry(&self) -> Entry<&T, PublicKey> {
+        Entry::new("cryptocurrency.fee_collector", &self.view)
+    }
+
+    /// Returns the public key of the configured fee collector, if any.
+    pub fn fee_collector(&self) -> Option<PublicKey> {
+        self.fee_collector_entry().get()
+    }
+
     /// Returns wallet for the given public key.
     pub fn wallet(&self, pub_key: &PublicKey) -> Option<Wallet> {
         self.wallets().get(pub_key)
     }
 
+    /// Returns `ProofMapIndex` with registered assets.
+    pub fn assets(&self) -> ProofMapIndex<&T, AssetId, Asset> {
+        ProofMapIndex::new("cryptocurrency.assets", &self.view)
+    }
+
+    /// Returns the asset registered under the given identifier, if any.
+    pub fn asset(&self, asset_id: &AssetId) -> Option<Asset> {
+        self.assets().get(asset_id)
+    }
+
+    /// Returns balances of the wallet with the given public key, indexed by asset.
+    ///
+    /// Stored as signed amounts, mirroring `Wallet.balance`, so that a balance can go
+    /// temporarily negative while covered by a matching frozen amount (see
+    /// `wallet_asset_balance`).
+    pub fn wallet_asset_balances(&self, public_key: &PublicKey) -> ProofMapIndex<&T, AssetId, i64> {
+        ProofMapIndex::new_in_family("cryptocurrency.wallet_asset_balances", public_key, &self.view)
+    }
+
+    /// Returns frozen balances of the wallet with the given public key, indexed by asset.
+    pub fn wallet_frozen_asset_balances(
+        &self,
+        public_key: &PublicKey,
+    ) -> ProofMapIndex<&T, AssetId, u64> {
+        ProofMapIndex::new_in_family(
+            "cryptocurrency.wallet_frozen_asset_balances",
+            public_key,
+            &self.view,
+        )
+    }
+
+    /// Returns the balance of the given asset held by the wallet, defaulting to zero.
+    ///
+    /// The native asset's balance is tracked on the `Wallet` itself rather than in
+    /// `wallet_asset_balances`. The balance is signed: it can go temporarily negative
+    /// while the shortfall is covered by a matching frozen amount (see
+    /// `can_confirm_withdrawal`), for both the native and non-native assets alike.
+    pub fn wallet_asset_balance(&self, public_key: &PublicKey, asset_id: &AssetId) -> i64 {
+        if *asset_id == native_asset_id() {
+            self.wallet(public_key).map_or(0, |wallet| wallet.balance)
+        } else {
+            self.wallet_asset_balances(public_key)
+                .get(asset_id)
+                .unwrap_or(0)
+        }
+    }
+
+    /// Returns the frozen balance of the given asset held by the wallet, defaulting to zero.
+    ///
+    /// The native asset's frozen balance is tracked on the `Wallet` itself rather than in
+    /// `wallet_frozen_asset_balances`.
+    pub fn wallet_frozen_asset_balance(&self, public_key: &PublicKey, asset_id: &AssetId) -> u64 {
+        if *asset_id == native_asset_id() {
+            self.wallet(public_key).map_or(0, |wallet| wallet.frozen_amount)
+        } else {
+            self.wallet_frozen_asset_balances(public_key)
+                .get(asset_id)
+                .unwrap_or(0)
+        }
+    }
+
+    /// Returns `ProofMapIndex` with pending swaps.
+    pub fn pending_swaps(&self) -> ProofMapIndex<&T, Hash, PendingSwap> {
+        ProofMapIndex::new("cryptocurrency.pending_swaps", &self.view)
+    }
+
+    /// Returns pending swap for the given offer transaction hash.
+    pub fn pending_swap(&self, offer_hash: &Hash) -> Option<PendingSwap> {
+        self.pending_swaps().get(offer_hash)
+    }
+
+    /// Returns a Merkle proof of the presence (or absence) of the pending swap with the
+    /// given offer transaction hash, provable against `state_hash`.
+    pub fn pending_swap_proof(&self, offer_hash: &Hash) -> MapProof<Hash, PendingSwap> {
+        self.pending_swaps().get_proof(*offer_hash)
+    }
+
     /// Returns the state hash of cryptocurrency service.
     pub fn state_hash(&self) -> Vec<Hash> {
-        vec![self.wallets().merkle_root()]
+        vec![
+            self.wallets().merkle_root(),
+            self.pending_transfers().merkle_root(),
+            self.pending_swaps().merkle_root(),
+        ]
     }
 }
 
@@ -85,6 +199,50 @@ impl<'a> Schema<&'a mut Fork> {
         ProofMapIndex::new("cryptocurrency.pending_transfers", self.view)
     }
 
+    /// Returns the mutable `Entry` holding the public key of the fee collector wallet.
+    pub fn fee_collector_entry_mut(&mut self) -> Entry<&mut Fork, PublicKey> {
+        Entry::new("cryptocurrency.fee_collector", &mut self.view)
+    }
+
+    /// Configures the wallet that collects fees paid by confirmed transfers.
+    pub fn set_fee_collector(&mut self, fee_collector: PublicKey) {
+        self.fee_collector_entry_mut().set(fee_collector);
+    }
+
+    /// Returns mutable `ProofMapIndex` with registered assets.
+    pub fn assets_mut(&mut self) -> ProofMapIndex<&mut Fork, AssetId, Asset> {
+        ProofMapIndex::new("cryptocurrency.assets", &mut self.view)
+    }
+
+    /// Returns mutable balances of the wallet with the given public key, indexed by asset.
+    pub fn wallet_asset_balances_mut(
+        &mut self,
+        public_key: &PublicKey,
+    ) -> ProofMapIndex<&mut Fork, AssetId, i64> {
+        ProofMapIndex::new_in_family(
+            "cryptocurrency.wallet_asset_balances",
+            public_key,
+            &mut self.view,
+        )
+    }
+
+    /// Returns mutable frozen balances of the wallet with the given public key, indexed by asset.
+    pub fn wallet_frozen_asset_balances_mut(
+        &mut self,
+        public_key: &PublicKey,
+    ) -> ProofMapIndex<&mut Fork, AssetId, u64> {
+        ProofMapIndex::new_in_family(
+            "cryptocurrency.wallet_frozen_asset_balances",
+            public_key,
+            &mut self.view,
+        )
+    }
+
+    /// Registers a new asset.
+    pub fn create_asset(&mut self, asset_id: AssetId, asset: Asset) {
+        self.assets_mut().put(&asset_id, asset);
+    }
+
     /// Returns history for the wallet by the given public key.
     pub fn wallet_history_mut(
         &mut self,
@@ -93,61 +251,203 @@ impl<'a> Schema<&'a mut Fork> {
         ProofListIndex::new_in_family("cryptocurrency.wallet_history", public_key, &mut self.view)
     }
 
-    /// Increase balance of the wallet and append new record to its history.
+    /// Increase balance of the wallet in the given asset and append new record to its history.
     ///
     /// Panics if there is no wallet with given public key.
-    pub fn increase_wallet_balance(&mut self, wallet: Wallet, amount: u64, transaction: &Hash) {
-        let wallet = {
-            let mut history = self.wallet_history_mut(&wallet.pub_key);
-            history.push(*transaction);
-            let history_hash = history.merkle_root();
-            let balance = wallet.balance;
-            wallet.set_balance(balance + amount, &history_hash)
-        };
-        self.wallets_mut().put(&wallet.pub_key, wallet.clone());
+    pub fn increase_wallet_balance(
+        &mut self,
+        wallet: Wallet,
+        asset_id: AssetId,
+        amount: u64,
+        transaction: &Hash,
+    ) {
+        if asset_id == native_asset_id() {
+            let wallet = {
+                let mut history = self.wallet_history_mut(&wallet.pub_key);
+                history.push(*transaction);
+                let history_hash = history.merkle_root();
+                let balance = wallet.balance;
+                wallet.set_balance(balance + amount, &history_hash)
+            };
+            self.wallets_mut().put(&wallet.pub_key, wallet.clone());
+        } else {
+            self.wallet_history_mut(&wallet.pub_key).push(*transaction);
+            let balance = self.wallet_asset_balance(&wallet.pub_key, &asset_id);
+            self.wallet_asset_balances_mut(&wallet.pub_key)
+                .put(&asset_id, balance + amount as i64);
+        }
     }
 
-    /// Decrease balance of the wallet and append new record to its history.
+    /// Decrease balance of the wallet in the given asset, freeze the withdrawn amount
+    /// and append new record to its history.
     ///
     /// Panics if there is no wallet with given public key.
-    pub fn decrease_wallet_balance(&mut self, wallet: Wallet, amount: u64, transaction: &Hash) {
-        let wallet = {
-            let mut history = self.wallet_history_mut(&wallet.pub_key);
-            history.push(*transaction);
-            let history_hash = history.merkle_root();
-            let balance = wallet.balance;
-            let frozen = wallet.frozen_amount;
-            wallet
-                .set_balance(balance - amount, &history_hash)
-                .set_frozen_amount(frozen + amount, &history_hash)
-        };
-        self.wallets_mut().put(&wallet.pub_key, wallet.clone());
+    pub fn decrease_wallet_balance(
+        &mut self,
+        wallet: Wallet,
+        asset_id: AssetId,
+        amount: u64,
+        transaction: &Hash,
+    ) {
+        if asset_id == native_asset_id() {
+            let wallet = {
+                let mut history = self.wallet_history_mut(&wallet.pub_key);
+                history.push(*transaction);
+                let history_hash = history.merkle_root();
+                let balance = wallet.balance;
+                let frozen = wallet.frozen_amount;
+                wallet
+                    .set_balance(balance - amount, &history_hash)
+                    .set_frozen_amount(frozen + amount, &history_hash)
+            };
+            self.wallets_mut().put(&wallet.pub_key, wallet.clone());
+        } else {
+            self.wallet_history_mut(&wallet.pub_key).push(*transaction);
+            let balance = self.wallet_asset_balance(&wallet.pub_key, &asset_id);
+            let frozen = self.wallet_frozen_asset_balance(&wallet.pub_key, &asset_id);
+            self.wallet_asset_balances_mut(&wallet.pub_key)
+                .put(&asset_id, balance - amount as i64);
+            self.wallet_frozen_asset_balances_mut(&wallet.pub_key)
+                .put(&asset_id, frozen + amount);
+        }
     }
 
-    /// Decrease frozen balance of the wallet and append new record to its history.
+    /// Decrease frozen balance of the wallet in the given asset and append new record to its history.
     ///
     /// Panics if there is no wallet with given public key.
-    pub fn decrease_wallet_frozen_balance(&mut self, wallet: Wallet, amount: u64, transaction: &Hash) {
-        let wallet = {
-            let mut history = self.wallet_history_mut(&wallet.pub_key);
-            history.push(*transaction);
-            let history_hash = history.merkle_root();
-            let frozen = wallet.frozen_amount;
-            wallet.set_frozen_amount(frozen - amount, &history_hash)
-        };
-        self.wallets_mut().put(&wallet.pub_key, wallet.clone());
+    pub fn decrease_wallet_frozen_balance(
+        &mut self,
+        wallet: Wallet,
+        asset_id: AssetId,
+        amount: u64,
+        transaction: &Hash,
+    ) {
+        if asset_id == native_asset_id() {
+            let wallet = {
+                let mut history = self.wallet_history_mut(&wallet.pub_key);
+                history.push(*transaction);
+                let history_hash = history.merkle_root();
+                let frozen = wallet.frozen_amount;
+                wallet.set_frozen_amount(frozen - amount, &history_hash)
+            };
+            self.wallets_mut().put(&wallet.pub_key, wallet.clone());
+        } else {
+            self.wallet_history_mut(&wallet.pub_key).push(*transaction);
+            let frozen = self.wallet_frozen_asset_balance(&wallet.pub_key, &asset_id);
+            self.wallet_frozen_asset_balances_mut(&wallet.pub_key)
+                .put(&asset_id, frozen - amount);
+        }
+    }
+
+    /// Immediately debit the wallet's available balance in the given asset, without freezing
+    /// it, and append new record to its history.
+    ///
+    /// Used for atomic operations (such as swaps) that debit and credit assets within a single
+    /// transaction, so there is nothing left pending to freeze. The balance may go temporarily
+    /// negative if the debited amount is covered by an unrelated frozen amount rather than the
+    /// available balance alone, same as `decrease_wallet_balance`.
+    ///
+    /// Panics if there is no wallet with given public key.
+    pub fn debit_wallet_balance(
+        &mut self,
+        wallet: Wallet,
+        asset_id: AssetId,
+        amount: u64,
+        transaction: &Hash,
+    ) {
+        if asset_id == native_asset_id() {
+            let wallet = {
+                let mut history = self.wallet_history_mut(&wallet.pub_key);
+                history.push(*transaction);
+                let history_hash = history.merkle_root();
+                let balance = wallet.balance;
+                wallet.set_balance(balance - amount, &history_hash)
+            };
+            self.wallets_mut().put(&wallet.pub_key, wallet.clone());
+        } else {
+            self.wallet_history_mut(&wallet.pub_key).push(*transaction);
+            let balance = self.wallet_asset_balance(&wallet.pub_key, &asset_id);
+            self.wallet_asset_balances_mut(&wallet.pub_key)
+                .put(&asset_id, balance - amount as i64);
+        }
     }
-    
+
+    /// Refund a previously frozen amount of the given asset back to the wallet's available
+    /// balance and append new record to its history.
+    ///
+    /// Panics if there is no wallet with given public key.
+    pub fn refund_frozen_balance(
+        &mut self,
+        wallet: Wallet,
+        asset_id: AssetId,
+        amount: u64,
+        transaction: &Hash,
+    ) {
+        if asset_id == native_asset_id() {
+            let wallet = {
+                let mut history = self.wallet_history_mut(&wallet.pub_key);
+                history.push(*transaction);
+                let history_hash = history.merkle_root();
+                let balance = wallet.balance;
+                let frozen = wallet.frozen_amount;
+                wallet
+                    .set_frozen_amount(frozen - amount, &history_hash)
+                    .set_balance(balance + amount, &history_hash)
+            };
+            self.wallets_mut().put(&wallet.pub_key, wallet.clone());
+        } else {
+            self.wallet_history_mut(&wallet.pub_key).push(*transaction);
+            let balance = self.wallet_asset_balance(&wallet.pub_key, &asset_id);
+            let frozen = self.wallet_frozen_asset_balance(&wallet.pub_key, &asset_id);
+            self.wallet_frozen_asset_balances_mut(&wallet.pub_key)
+                .put(&asset_id, frozen - amount);
+            self.wallet_asset_balances_mut(&wallet.pub_key)
+                .put(&asset_id, balance + amount as i64);
+        }
+    }
+
     /// Fulfill pending transfer
     pub fn fulfill_pending_transfer(&mut self, transfer: PendingTransfer) {
         let fulfilled_transfer = transfer.set_fulfilled();
-        
+
         self.pending_transfers_mut().put(&fulfilled_transfer.tx_hash, fulfilled_transfer.clone());
     }
 
+    /// Mark pending transfer as cancelled. The frozen amount is not refunded here; see
+    /// `refund_frozen_balance`.
+    pub fn cancel_pending_transfer(&mut self, transfer: PendingTransfer) {
+        let cancelled_transfer = transfer.set_cancelled();
+
+        self.pending_transfers_mut().put(&cancelled_transfer.tx_hash, cancelled_transfer.clone());
+    }
+
     /// Create new pending transfer
-    pub fn create_pending_transfer(&mut self, tx_hash: Hash, from: &PublicKey, to: &PublicKey, amount: u64) {
-        self.pending_transfers_mut().put(&tx_hash, PendingTransfer::new(tx_hash, from, to, amount, false));
+    pub fn create_pending_transfer(
+        &mut self,
+        tx_hash: Hash,
+        from: &PublicKey,
+        to: &PublicKey,
+        approver: &PublicKey,
+        asset_id: AssetId,
+        amount: u64,
+        fee: u64,
+        deadline_height: u64,
+    ) {
+        self.pending_transfers_mut().put(
+            &tx_hash,
+            PendingTransfer::new(
+                tx_hash,
+                from,
+                to,
+                approver,
+                asset_id,
+                amount,
+                fee,
+                deadline_height,
+                false,
+                false,
+            ),
+        );
     }
 
     /// Create new wallet and append first record to its history.
@@ -160,4 +460,53 @@ impl<'a> Schema<&'a mut Fork> {
         };
         self.wallets_mut().put(key, wallet);
     }
+
+    /// Returns mutable `ProofMapIndex` with pending swaps.
+    pub fn pending_swaps_mut(&mut self) -> ProofMapIndex<&mut Fork, Hash, PendingSwap> {
+        ProofMapIndex::new("cryptocurrency.pending_swaps", self.view)
+    }
+
+    /// Create new pending swap offer.
+    pub fn create_pending_swap(
+        &mut self,
+        offer_hash: Hash,
+        maker: &PublicKey,
+        taker: &PublicKey,
+        offered_asset: AssetId,
+        offered_amount: u64,
+        wanted_asset: AssetId,
+        wanted_amount: u64,
+        deadline_height: u64,
+    ) {
+        self.pending_swaps_mut().put(
+            &offer_hash,
+            PendingSwap::new(
+                offer_hash,
+                maker,
+                taker,
+                offered_asset,
+                offered_amount,
+                wanted_asset,
+                wanted_amount,
+                deadline_height,
+                false,
+                false,
+            ),
+        );
+    }
+
+    /// Fulfill pending swap.
+    pub fn fulfill_pending_swap(&mut self, swap: PendingSwap) {
+        let fulfilled_swap = swap.set_fulfilled();
+
+        self.pending_swaps_mut().put(&fulfilled_swap.offer_hash, fulfilled_swap.clone());
+    }
+
+    /// Mark pending swap offer as cancelled. The frozen amount is not refunded here; see
+    /// `refund_frozen_balance`.
+    pub fn cancel_pending_swap(&mut self, swap: PendingSwap) {
+        let cancelled_swap = swap.set_cancelled();
+
+        self.pending_swaps_mut().put(&cancelled_swap.offer_hash, cancelled_swap.clone());
+    }
 }
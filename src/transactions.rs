@@ -19,13 +19,17 @@
 #![allow(bare_trait_objects)]
 
 use exonum::{
-    blockchain::{ExecutionError, ExecutionResult, Transaction, TransactionContext},
+    blockchain::{ExecutionError, ExecutionResult, Schema as CoreSchema, Transaction, TransactionContext},
     crypto::{Hash, PublicKey, SecretKey},
     messages::{Message, RawTransaction, Signed},
 };
 
 use super::proto;
-use crate::{schema::Schema, CRYPTOCURRENCY_SERVICE_ID};
+use crate::{
+    asset::{native_asset_id, Asset, AssetId},
+    schema::Schema,
+    CRYPTOCURRENCY_SERVICE_ID,
+};
 
 const ERROR_SENDER_SAME_AS_RECEIVER: u8 = 0;
 
@@ -33,6 +37,12 @@ const ERROR_THIRD_PARTY_SAME_AS_SENDER_OR_RECEIVER: u8 = 1;
 
 const ERROR_UNEXPECTED_THIRD_PARTY: u8 = 2;
 
+/// Number of blocks a pending transfer stays locked before its sender is allowed to cancel it.
+const TRANSFER_TIMEOUT_HEIGHT: u64 = 1440;
+
+/// Number of blocks a pending swap offer stays locked before its maker is allowed to cancel it.
+const SWAP_TIMEOUT_HEIGHT: u64 = 1440;
+
 /// Error codes emitted by wallet transactions during execution.
 #[derive(Debug, Fail)]
 #[repr(u8)]
@@ -77,7 +87,73 @@ pub enum Error {
     ///
     /// Can be emitted by `Transfer`.
     #[fail(display = "Approver doesn't exist")]
-    ApproverNotFound = 6,    
+    ApproverNotFound = 6,
+
+    /// Fee collector wallet is not configured or doesn't exist.
+    ///
+    /// Can be emitted by `ConfirmTransfer`.
+    #[fail(display = "Fee collector doesn't exist")]
+    FeeCollectorNotFound = 7,
+
+    /// Asset is not registered.
+    ///
+    /// Can be emitted by `Transfer` or `SwapOffer`.
+    #[fail(display = "Asset doesn't exist")]
+    AssetNotFound = 8,
+
+    /// Asset with the given identifier is already registered.
+    ///
+    /// Can be emitted by `IssueAsset`.
+    #[fail(display = "Asset already exists")]
+    AssetAlreadyExists = 9,
+
+    /// Pending transfer has already been cancelled.
+    ///
+    /// Can be emitted by `ConfirmTransfer` or `CancelTransfer`.
+    #[fail(display = "Pending transfer has already been cancelled")]
+    PendingTransferAlreadyCancelled = 10,
+
+    /// Sender tried to cancel a pending transfer before its deadline height was reached.
+    ///
+    /// Can be emitted by `CancelTransfer`.
+    #[fail(display = "Pending transfer cannot be cancelled before its deadline height")]
+    DeadlineNotReached = 11,
+
+    /// Swap offer doesn't exist.
+    ///
+    /// Can be emitted by `AcceptSwap` or `CancelSwap`.
+    #[fail(display = "Swap offer doesn't exist")]
+    SwapOfferNotFound = 12,
+
+    /// Swap offer has already been fulfilled.
+    ///
+    /// Can be emitted by `AcceptSwap` or `CancelSwap`.
+    #[fail(display = "Swap offer has already been fulfilled")]
+    SwapOfferAlreadyFulfilled = 13,
+
+    /// Taker doesn't exist.
+    ///
+    /// Can be emitted by `SwapOffer`.
+    #[fail(display = "Taker doesn't exist")]
+    TakerNotFound = 14,
+
+    /// Swap offer has already been cancelled.
+    ///
+    /// Can be emitted by `AcceptSwap` or `CancelSwap`.
+    #[fail(display = "Swap offer has already been cancelled")]
+    SwapOfferAlreadyCancelled = 15,
+
+    /// Maker tried to cancel a swap offer before its deadline height was reached.
+    ///
+    /// Can be emitted by `CancelSwap`.
+    #[fail(display = "Swap offer cannot be cancelled before its deadline height")]
+    SwapDeadlineNotReached = 16,
+
+    /// Sum of the transfer amount and fee overflows a `u64`.
+    ///
+    /// Can be emitted by `Transfer`.
+    #[fail(display = "Transfer amount and fee are too large to add together")]
+    AmountOverflow = 17,
 }
 
 impl From<Error> for ExecutionError {
@@ -95,8 +171,26 @@ pub struct Transfer {
     pub to: PublicKey,
     /// `PublicKey` of approver's wallet.
     pub approver: PublicKey,
+    /// Asset being transferred.
+    pub asset_id: AssetId,
     /// Amount of currency to transfer.
     pub amount: u64,
+    /// Fee paid to the fee collector once the transfer is confirmed.
+    pub fee: u64,
+    /// Auxiliary number to guarantee [non-idempotence][idempotence] of transactions.
+    ///
+    /// [idempotence]: https://en.wikipedia.org/wiki/Idempotence
+    pub seed: u64,
+}
+
+/// Register a new asset and credit its whole `supply` to the issuer's wallet.
+#[derive(Serialize, Deserialize, Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::IssueAsset")]
+pub struct IssueAsset {
+    /// Human-readable name of the asset.
+    pub name: String,
+    /// Total supply to issue, credited to the issuer.
+    pub supply: u64,
     /// Auxiliary number to guarantee [non-idempotence][idempotence] of transactions.
     ///
     /// [idempotence]: https://en.wikipedia.org/wiki/Idempotence
@@ -134,6 +228,61 @@ pub struct ConfirmTransfer {
     pub seed: u64,
 }
 
+/// Cancel pending transfer transaction with the given `tx_hash`, refunding the frozen amount.
+#[derive(Serialize, Deserialize, Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::CancelTransfer")]
+pub struct CancelTransfer {
+    /// Hash of the transfer transaction to be cancelled
+    pub tx_hash: Hash,
+    /// [idempotence]: https://en.wikipedia.org/wiki/Idempotence
+    pub seed: u64,
+}
+
+/// Offer to atomically swap `offered_amount` of `offered_asset` for `wanted_amount` of
+/// `wanted_asset` with the named `counterparty`. Freezes `offered_amount` in the maker's wallet.
+#[derive(Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::SwapOffer", serde_pb_convert)]
+pub struct SwapOffer {
+    /// Asset offered by the maker.
+    pub offered_asset: AssetId,
+    /// Amount of `offered_asset` to freeze and exchange.
+    pub offered_amount: u64,
+    /// Asset requested from the counterparty.
+    pub wanted_asset: AssetId,
+    /// Amount of `wanted_asset` the counterparty must pay.
+    pub wanted_amount: u64,
+    /// Public key of the only wallet allowed to accept this offer.
+    pub counterparty: PublicKey,
+    /// Auxiliary number to guarantee [non-idempotence][idempotence] of transactions.
+    ///
+    /// [idempotence]: https://en.wikipedia.org/wiki/Idempotence
+    pub seed: u64,
+}
+
+/// Accept the swap offer identified by `offer_hash`, atomically exchanging both assets.
+#[derive(Serialize, Deserialize, Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::AcceptSwap")]
+pub struct AcceptSwap {
+    /// Hash of the `SwapOffer` transaction to accept.
+    pub offer_hash: Hash,
+    /// Auxiliary number to guarantee [non-idempotence][idempotence] of transactions.
+    ///
+    /// [idempotence]: https://en.wikipedia.org/wiki/Idempotence
+    pub seed: u64,
+}
+
+/// Cancel the swap offer identified by `offer_hash`, refunding the frozen amount to the maker.
+#[derive(Serialize, Deserialize, Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::CancelSwap")]
+pub struct CancelSwap {
+    /// Hash of the `SwapOffer` transaction to cancel.
+    pub offer_hash: Hash,
+    /// Auxiliary number to guarantee [non-idempotence][idempotence] of transactions.
+    ///
+    /// [idempotence]: https://en.wikipedia.org/wiki/Idempotence
+    pub seed: u64,
+}
+
 /// Transaction group.
 #[derive(Serialize, Deserialize, Clone, Debug, TransactionSet)]
 pub enum WalletTransactions {
@@ -145,6 +294,16 @@ pub enum WalletTransactions {
     CreateWallet(CreateWallet),
     /// ConfirmTransfer tx.
     ConfirmTransfer(ConfirmTransfer),
+    /// IssueAsset tx.
+    IssueAsset(IssueAsset),
+    /// CancelTransfer tx.
+    CancelTransfer(CancelTransfer),
+    /// SwapOffer tx.
+    SwapOffer(SwapOffer),
+    /// AcceptSwap tx.
+    AcceptSwap(AcceptSwap),
+    /// CancelSwap tx.
+    CancelSwap(CancelSwap),
 }
 
 impl ConfirmTransfer {
@@ -164,6 +323,57 @@ impl ConfirmTransfer {
     }
 }
 
+impl CancelTransfer {
+    #[doc(hidden)]
+    pub fn sign(
+        pk: &PublicKey, &tx_hash: &Hash, seed: u64, sk: &SecretKey
+    ) -> Signed<RawTransaction> {
+        Message::sign_transaction(
+            Self {
+                tx_hash,
+                seed,
+            },
+            CRYPTOCURRENCY_SERVICE_ID,
+            *pk,
+            sk
+        )
+    }
+}
+
+impl AcceptSwap {
+    #[doc(hidden)]
+    pub fn sign(
+        pk: &PublicKey, &offer_hash: &Hash, seed: u64, sk: &SecretKey
+    ) -> Signed<RawTransaction> {
+        Message::sign_transaction(
+            Self {
+                offer_hash,
+                seed,
+            },
+            CRYPTOCURRENCY_SERVICE_ID,
+            *pk,
+            sk
+        )
+    }
+}
+
+impl CancelSwap {
+    #[doc(hidden)]
+    pub fn sign(
+        pk: &PublicKey, &offer_hash: &Hash, seed: u64, sk: &SecretKey
+    ) -> Signed<RawTransaction> {
+        Message::sign_transaction(
+            Self {
+                offer_hash,
+                seed,
+            },
+            CRYPTOCURRENCY_SERVICE_ID,
+            *pk,
+            sk
+        )
+    }
+}
+
 impl CreateWallet {
     #[doc(hidden)]
     pub fn sign(name: &str, pk: &PublicKey, sk: &SecretKey) -> Signed<RawTransaction> {
@@ -184,12 +394,58 @@ impl Transfer {
         pk: &PublicKey,
         &to: &PublicKey,
         &approver: &PublicKey,
+        asset_id: AssetId,
         amount: u64,
+        fee: u64,
         seed: u64,
         sk: &SecretKey,
     ) -> Signed<RawTransaction> {
         Message::sign_transaction(
-            Self { to, amount, approver, seed },
+            Self { to, asset_id, amount, fee, approver, seed },
+            CRYPTOCURRENCY_SERVICE_ID,
+            *pk,
+            sk,
+        )
+    }
+}
+
+impl IssueAsset {
+    #[doc(hidden)]
+    pub fn sign(name: &str, supply: u64, seed: u64, pk: &PublicKey, sk: &SecretKey) -> Signed<RawTransaction> {
+        Message::sign_transaction(
+            Self {
+                name: name.to_owned(),
+                supply,
+                seed,
+            },
+            CRYPTOCURRENCY_SERVICE_ID,
+            *pk,
+            sk,
+        )
+    }
+}
+
+impl SwapOffer {
+    #[doc(hidden)]
+    pub fn sign(
+        pk: &PublicKey,
+        offered_asset: AssetId,
+        offered_amount: u64,
+        wanted_asset: AssetId,
+        wanted_amount: u64,
+        &counterparty: &PublicKey,
+        seed: u64,
+        sk: &SecretKey,
+    ) -> Signed<RawTransaction> {
+        Message::sign_transaction(
+            Self {
+                offered_asset,
+                offered_amount,
+                wanted_asset,
+                wanted_amount,
+                counterparty,
+                seed,
+            },
             CRYPTOCURRENCY_SERVICE_ID,
             *pk,
             sk,
@@ -202,11 +458,16 @@ impl Transaction for Transfer {
         let from = &context.author();
         let hash = context.tx_hash();
 
+        let current_height = CoreSchema::new(context.fork()).height().0;
+
         let mut schema = Schema::new(context.fork());
 
         let to = &self.to;
         let approver = &self.approver;
+        let asset_id = self.asset_id;
         let amount = self.amount;
+        let fee = self.fee;
+        let total = amount.checked_add(fee).ok_or(Error::AmountOverflow)?;
 
         if from == approver || to == approver {
             return Err(ExecutionError::new(ERROR_THIRD_PARTY_SAME_AS_SENDER_OR_RECEIVER))
@@ -216,19 +477,35 @@ impl Transaction for Transfer {
             return Err(ExecutionError::new(ERROR_SENDER_SAME_AS_RECEIVER));
         }
 
+        if asset_id != native_asset_id() {
+            schema.asset(&asset_id).ok_or(Error::AssetNotFound)?;
+        }
+
         schema.wallet(approver).ok_or(Error::ApproverNotFound)?;
         let sender = schema.wallet(from).ok_or(Error::SenderNotFound)?;
 
         schema.wallet(to).ok_or(Error::ReceiverNotFound)?;
 
+        let balance = schema.wallet_asset_balance(from, &asset_id);
+        let frozen = schema.wallet_frozen_asset_balance(from, &asset_id);
+
         // considering frozen_amount to still be awailable for withdrawal,
         // since it can be left unconfirmed
-        if sender.balance + (sender.frozen_amount as i64) < (amount as i64) {
+        if balance + (frozen as i64) < (total as i64) {
             Err(Error::InsufficientCurrencyAmount)?
         }
 
-        schema.decrease_wallet_balance(sender, amount, &hash);
-        schema.create_pending_transfer(hash, from, to, approver, amount);
+        schema.decrease_wallet_balance(sender, asset_id, total, &hash);
+        schema.create_pending_transfer(
+            hash,
+            from,
+            to,
+            approver,
+            asset_id,
+            amount,
+            fee,
+            current_height + TRANSFER_TIMEOUT_HEIGHT,
+        );
 
         Ok(())
     }
@@ -237,8 +514,9 @@ impl Transaction for Transfer {
 /// checking if withdrawal can be confirmed.
 /// handling withdrawal amount which is greater than frozen amount
 /// and also the situation when the frozen balance is greater than initial balance
-pub fn can_confirm_withdrawal(balance: i64, frozen: u64, amount: u64) -> bool {
-    frozen >= amount && (frozen as i64) + balance >= (amount as i64)
+pub fn can_confirm_withdrawal(balance: i64, frozen: u64, amount: u64, fee: u64) -> bool {
+    let total = amount + fee;
+    frozen >= total && (frozen as i64) + balance >= (total as i64)
 }
 
 impl Transaction for ConfirmTransfer {
@@ -252,32 +530,98 @@ impl Transaction for ConfirmTransfer {
             if pending_transfer.approver != *approver {
                 return Err(ExecutionError::new(ERROR_UNEXPECTED_THIRD_PARTY))
             }
-            
+
             if pending_transfer.fulfilled {
                 Err(Error::PendingTransferAlreadyFulfilled)?
             }
-            
+
+            if pending_transfer.cancelled {
+                Err(Error::PendingTransferAlreadyCancelled)?
+            }
+
             let from = &pending_transfer.from;
             let to = &pending_transfer.to;
 
             let sender = schema.wallet(from).ok_or(Error::SenderNotFound)?;
             let receiver = schema.wallet(to).ok_or(Error::ReceiverNotFound)?;
 
+            let asset_id = pending_transfer.asset_id;
             let amount = pending_transfer.amount;
+            let fee = pending_transfer.fee;
+
+            let balance = schema.wallet_asset_balance(from, &asset_id);
+            let frozen = schema.wallet_frozen_asset_balance(from, &asset_id);
+
+            if !can_confirm_withdrawal(balance, frozen, amount, fee) {
+                Err(Error::InsufficientCurrencyAmount)?
+            }
+
+            schema.decrease_wallet_frozen_balance(sender, asset_id, amount + fee, &hash);
+            schema.increase_wallet_balance(receiver, asset_id, amount, &hash);
 
-            if !can_confirm_withdrawal(sender.balance, sender.frozen_amount, amount) {
-                Err(Error::InsufficientCurrencyAmount)?                
+            if fee > 0 {
+                let fee_collector_key = schema.fee_collector().ok_or(Error::FeeCollectorNotFound)?;
+                let fee_collector = schema
+                    .wallet(&fee_collector_key)
+                    .ok_or(Error::FeeCollectorNotFound)?;
+                schema.increase_wallet_balance(fee_collector, asset_id, fee, &hash);
             }
 
-            schema.decrease_wallet_frozen_balance(sender, amount, &hash);
-            schema.increase_wallet_balance(receiver, amount, &hash);
             schema.fulfill_pending_transfer(pending_transfer);
-            
+
             Ok(())
         } else {
             Err(Error::PendingTransferNotFound)?
         }
-    }    
+    }
+}
+
+impl Transaction for CancelTransfer {
+    fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+        let author = &context.author();
+        let hash = context.tx_hash();
+
+        let current_height = CoreSchema::new(context.fork()).height().0;
+
+        let mut schema = Schema::new(context.fork());
+
+        let pending_transfer = schema
+            .pending_transfer(&self.tx_hash)
+            .ok_or(Error::PendingTransferNotFound)?;
+
+        if pending_transfer.fulfilled {
+            Err(Error::PendingTransferAlreadyFulfilled)?
+        }
+
+        if pending_transfer.cancelled {
+            Err(Error::PendingTransferAlreadyCancelled)?
+        }
+
+        let is_approver = *author == pending_transfer.approver;
+        let is_sender_past_deadline =
+            *author == pending_transfer.from && current_height > pending_transfer.deadline_height;
+
+        if !is_approver && !is_sender_past_deadline {
+            if *author == pending_transfer.from {
+                return Err(Error::DeadlineNotReached.into());
+            }
+            return Err(ExecutionError::new(ERROR_UNEXPECTED_THIRD_PARTY));
+        }
+
+        let sender = schema
+            .wallet(&pending_transfer.from)
+            .ok_or(Error::SenderNotFound)?;
+
+        schema.refund_frozen_balance(
+            sender,
+            pending_transfer.asset_id,
+            pending_transfer.amount + pending_transfer.fee,
+            &hash,
+        );
+        schema.cancel_pending_transfer(pending_transfer);
+
+        Ok(())
+    }
 }
 
 impl Transaction for Issue {
@@ -290,7 +634,7 @@ impl Transaction for Issue {
         if let Some(wallet) = schema.wallet(pub_key) {
             let amount = self.amount;
 
-            schema.increase_wallet_balance(wallet, amount, &hash);
+            schema.increase_wallet_balance(wallet, native_asset_id(), amount, &hash);
             Ok(())
         } else {
             Err(Error::ReceiverNotFound)?
@@ -298,6 +642,28 @@ impl Transaction for Issue {
     }
 }
 
+impl Transaction for IssueAsset {
+    fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+        let issuer = &context.author();
+        let hash = context.tx_hash();
+
+        let mut schema = Schema::new(context.fork());
+
+        let wallet = schema.wallet(issuer).ok_or(Error::SenderNotFound)?;
+
+        let asset_id = hash;
+        if schema.asset(&asset_id).is_some() {
+            Err(Error::AssetAlreadyExists)?
+        }
+
+        let asset = Asset::new(&self.name, issuer, self.supply);
+        schema.create_asset(asset_id, asset);
+        schema.increase_wallet_balance(wallet, asset_id, self.supply, &hash);
+
+        Ok(())
+    }
+}
+
 impl Transaction for CreateWallet {
     fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
         let pub_key = &context.author();
@@ -314,3 +680,168 @@ impl Transaction for CreateWallet {
         }
     }
 }
+
+impl Transaction for SwapOffer {
+    fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+        let maker = &context.author();
+        let hash = context.tx_hash();
+
+        let current_height = CoreSchema::new(context.fork()).height().0;
+
+        let mut schema = Schema::new(context.fork());
+
+        let taker = &self.counterparty;
+        let offered_asset = self.offered_asset;
+        let offered_amount = self.offered_amount;
+        let wanted_asset = self.wanted_asset;
+        let wanted_amount = self.wanted_amount;
+
+        if maker == taker {
+            return Err(ExecutionError::new(ERROR_SENDER_SAME_AS_RECEIVER));
+        }
+
+        if offered_asset != native_asset_id() {
+            schema.asset(&offered_asset).ok_or(Error::AssetNotFound)?;
+        }
+        if wanted_asset != native_asset_id() {
+            schema.asset(&wanted_asset).ok_or(Error::AssetNotFound)?;
+        }
+
+        let maker_wallet = schema.wallet(maker).ok_or(Error::SenderNotFound)?;
+        schema.wallet(taker).ok_or(Error::TakerNotFound)?;
+
+        let balance = schema.wallet_asset_balance(maker, &offered_asset);
+        let frozen = schema.wallet_frozen_asset_balance(maker, &offered_asset);
+
+        // Unlike `can_confirm_withdrawal`, nothing has frozen the maker's funds yet here, so
+        // the check is against the maker's available balance plus whatever is already frozen
+        // from other pending operations -- not against the offered_amount having been frozen.
+        if balance + (frozen as i64) < (offered_amount as i64) {
+            Err(Error::InsufficientCurrencyAmount)?
+        }
+
+        schema.decrease_wallet_balance(maker_wallet, offered_asset, offered_amount, &hash);
+        schema.create_pending_swap(
+            hash,
+            maker,
+            taker,
+            offered_asset,
+            offered_amount,
+            wanted_asset,
+            wanted_amount,
+            current_height + SWAP_TIMEOUT_HEIGHT,
+        );
+
+        Ok(())
+    }
+}
+
+impl Transaction for AcceptSwap {
+    fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+        let taker = &context.author();
+        let hash = context.tx_hash();
+
+        let mut schema = Schema::new(context.fork());
+
+        let swap = schema
+            .pending_swap(&self.offer_hash)
+            .ok_or(Error::SwapOfferNotFound)?;
+
+        if swap.fulfilled {
+            Err(Error::SwapOfferAlreadyFulfilled)?
+        }
+
+        if swap.cancelled {
+            Err(Error::SwapOfferAlreadyCancelled)?
+        }
+
+        if *taker != swap.taker {
+            return Err(ExecutionError::new(ERROR_UNEXPECTED_THIRD_PARTY));
+        }
+
+        schema.wallet(&swap.maker).ok_or(Error::SenderNotFound)?;
+        let taker_wallet = schema.wallet(taker).ok_or(Error::ReceiverNotFound)?;
+
+        let taker_balance = schema.wallet_asset_balance(taker, &swap.wanted_asset);
+        let taker_frozen = schema.wallet_frozen_asset_balance(taker, &swap.wanted_asset);
+
+        // As in `SwapOffer::execute`, nothing has frozen the taker's funds yet, so check
+        // against their available balance plus whatever is already frozen, not against
+        // wanted_amount having been frozen (which `can_confirm_withdrawal` assumes).
+        if taker_balance + (taker_frozen as i64) < (swap.wanted_amount as i64) {
+            Err(Error::InsufficientCurrencyAmount)?
+        }
+
+        // Debit the taker's offer and credit it to the maker.
+        schema.debit_wallet_balance(taker_wallet, swap.wanted_asset, swap.wanted_amount, &hash);
+        let maker_wallet_after_credit = schema
+            .wallet(&swap.maker)
+            .ok_or(Error::SenderNotFound)?;
+        schema.increase_wallet_balance(
+            maker_wallet_after_credit,
+            swap.wanted_asset,
+            swap.wanted_amount,
+            &hash,
+        );
+
+        // Release the maker's frozen offer to the taker. Re-fetch the maker's wallet so the
+        // balance credited above isn't clobbered by a stale snapshot when offered_asset and
+        // wanted_asset are both the native asset.
+        let maker_wallet_before_release = schema.wallet(&swap.maker).ok_or(Error::SenderNotFound)?;
+        schema.decrease_wallet_frozen_balance(
+            maker_wallet_before_release,
+            swap.offered_asset,
+            swap.offered_amount,
+            &hash,
+        );
+        let taker_wallet_after_debit = schema.wallet(taker).ok_or(Error::ReceiverNotFound)?;
+        schema.increase_wallet_balance(
+            taker_wallet_after_debit,
+            swap.offered_asset,
+            swap.offered_amount,
+            &hash,
+        );
+
+        schema.fulfill_pending_swap(swap);
+
+        Ok(())
+    }
+}
+
+impl Transaction for CancelSwap {
+    fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+        let author = &context.author();
+        let hash = context.tx_hash();
+
+        let current_height = CoreSchema::new(context.fork()).height().0;
+
+        let mut schema = Schema::new(context.fork());
+
+        let swap = schema
+            .pending_swap(&self.offer_hash)
+            .ok_or(Error::SwapOfferNotFound)?;
+
+        if swap.fulfilled {
+            Err(Error::SwapOfferAlreadyFulfilled)?
+        }
+
+        if swap.cancelled {
+            Err(Error::SwapOfferAlreadyCancelled)?
+        }
+
+        if *author != swap.maker {
+            return Err(ExecutionError::new(ERROR_UNEXPECTED_THIRD_PARTY));
+        }
+
+        if current_height <= swap.deadline_height {
+            Err(Error::SwapDeadlineNotReached)?
+        }
+
+        let maker_wallet = schema.wallet(&swap.maker).ok_or(Error::SenderNotFound)?;
+
+        schema.refund_frozen_balance(maker_wallet, swap.offered_asset, swap.offered_amount, &hash);
+        schema.cancel_pending_swap(swap);
+
+        Ok(())
+    }
+}
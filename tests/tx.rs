@@ -4,21 +4,613 @@ mod tx {
 
     #[test]
     fn can_withdraw_with_sufficient_frozen() {
-        assert!(can_confirm_withdrawal(100, 100, 10));
+        assert!(can_confirm_withdrawal(100, 100, 10, 0));
     }
 
     #[test]
     fn can_not_withdraw_with_insufficient_frozen() {
-        assert!(!can_confirm_withdrawal(100, 2, 10));
+        assert!(!can_confirm_withdrawal(100, 2, 10, 0));
     }
 
     #[test]
     fn can_not_withdraw_with_insufficient_original_balance() {
-        assert!(!can_confirm_withdrawal(-100, 20, 10));
+        assert!(!can_confirm_withdrawal(-100, 20, 10, 0));
     }
 
     #[test]
     fn can_withdraw_with_sufficient_original_balance() {
-        assert!(can_confirm_withdrawal(-100, 120, 10));
+        assert!(can_confirm_withdrawal(-100, 120, 10, 0));
+    }
+
+    #[test]
+    fn can_not_withdraw_when_fee_pushes_total_above_frozen() {
+        assert!(!can_confirm_withdrawal(100, 15, 10, 10));
+    }
+
+    #[test]
+    fn can_withdraw_when_frozen_covers_amount_and_fee() {
+        assert!(can_confirm_withdrawal(100, 20, 10, 10));
+    }
+}
+
+#[cfg(test)]
+mod asset_balances {
+    use exonum::{
+        crypto::{gen_keypair, Hash},
+        storage::{Database, MemoryDB},
+    };
+    use exonum_cryptocurrency_advanced::{
+        asset::{native_asset_id, Asset},
+        schema::Schema,
+    };
+
+    #[test]
+    fn native_balance_is_tracked_on_the_wallet_itself() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (pub_key, _) = gen_keypair();
+        let tx_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_wallet(&pub_key, "Alice", &tx_hash);
+
+        let balance = schema.wallet(&pub_key).unwrap().balance;
+        assert_eq!(schema.wallet_asset_balance(&pub_key, &native_asset_id()), balance);
+    }
+
+    #[test]
+    fn non_native_balance_defaults_to_zero_for_unknown_asset() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (pub_key, _) = gen_keypair();
+        let asset_id = Hash::new([1; 32]);
+
+        let schema = Schema::new(&mut fork);
+        assert_eq!(schema.wallet_asset_balance(&pub_key, &asset_id), 0);
+        assert_eq!(schema.wallet_frozen_asset_balance(&pub_key, &asset_id), 0);
+    }
+
+    #[test]
+    fn decrease_non_native_balance_can_go_negative_when_covered_by_frozen_amount() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (pub_key, _) = gen_keypair();
+        let (issuer, _) = gen_keypair();
+        let asset_id = Hash::new([2; 32]);
+        let tx_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_wallet(&pub_key, "Alice", &tx_hash);
+        schema.create_asset(asset_id, Asset::new("token", &issuer, 100));
+
+        let wallet = schema.wallet(&pub_key).unwrap();
+        schema.increase_wallet_balance(wallet, asset_id, 10, &tx_hash);
+
+        // Freezing more than the available balance used to panic/wrap with an
+        // unsigned balance; the shortfall is covered by the frozen amount itself,
+        // same as how the native asset's signed balance behaves.
+        let wallet = schema.wallet(&pub_key).unwrap();
+        schema.decrease_wallet_balance(wallet, asset_id, 15, &tx_hash);
+
+        assert_eq!(schema.wallet_asset_balance(&pub_key, &asset_id), -5);
+        assert_eq!(schema.wallet_frozen_asset_balance(&pub_key, &asset_id), 15);
+    }
+
+    #[test]
+    fn debit_non_native_balance_can_go_negative_when_covered_by_frozen_amount() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (pub_key, _) = gen_keypair();
+        let (issuer, _) = gen_keypair();
+        let asset_id = Hash::new([3; 32]);
+        let tx_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_wallet(&pub_key, "Alice", &tx_hash);
+        schema.create_asset(asset_id, Asset::new("token", &issuer, 100));
+
+        let wallet = schema.wallet(&pub_key).unwrap();
+        schema.increase_wallet_balance(wallet, asset_id, 10, &tx_hash);
+
+        // An atomic debit (used by swaps) doesn't touch the frozen amount, so an
+        // unrelated frozen balance can still cover the shortfall here too.
+        let wallet = schema.wallet(&pub_key).unwrap();
+        schema.debit_wallet_balance(wallet, asset_id, 15, &tx_hash);
+
+        assert_eq!(schema.wallet_asset_balance(&pub_key, &asset_id), -5);
+    }
+
+    #[test]
+    fn refund_frozen_balance_restores_non_native_balance() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (pub_key, _) = gen_keypair();
+        let (issuer, _) = gen_keypair();
+        let asset_id = Hash::new([4; 32]);
+        let tx_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_wallet(&pub_key, "Alice", &tx_hash);
+        schema.create_asset(asset_id, Asset::new("token", &issuer, 100));
+
+        let wallet = schema.wallet(&pub_key).unwrap();
+        schema.increase_wallet_balance(wallet, asset_id, 10, &tx_hash);
+        let wallet = schema.wallet(&pub_key).unwrap();
+        schema.decrease_wallet_balance(wallet, asset_id, 10, &tx_hash);
+
+        let wallet = schema.wallet(&pub_key).unwrap();
+        schema.refund_frozen_balance(wallet, asset_id, 10, &tx_hash);
+
+        assert_eq!(schema.wallet_asset_balance(&pub_key, &asset_id), 10);
+        assert_eq!(schema.wallet_frozen_asset_balance(&pub_key, &asset_id), 0);
+    }
+}
+
+#[cfg(test)]
+mod pending_transfers {
+    use exonum::{
+        crypto::{gen_keypair, Hash},
+        storage::{Database, MemoryDB},
+    };
+    use exonum_cryptocurrency_advanced::{asset::native_asset_id, schema::Schema};
+
+    #[test]
+    fn create_pending_transfer_stores_the_deadline_height() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (from, _) = gen_keypair();
+        let (to, _) = gen_keypair();
+        let (approver, _) = gen_keypair();
+        let tx_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_pending_transfer(
+            tx_hash,
+            &from,
+            &to,
+            &approver,
+            native_asset_id(),
+            10,
+            1,
+            1500,
+        );
+
+        let pending_transfer = schema.pending_transfer(&tx_hash).unwrap();
+        assert_eq!(pending_transfer.deadline_height, 1500);
+        assert!(!pending_transfer.fulfilled);
+        assert!(!pending_transfer.cancelled);
+    }
+
+    #[test]
+    fn fulfill_and_cancel_pending_transfer_set_the_respective_flag() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (from, _) = gen_keypair();
+        let (to, _) = gen_keypair();
+        let (approver, _) = gen_keypair();
+        let tx_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_pending_transfer(tx_hash, &from, &to, &approver, native_asset_id(), 10, 1, 100);
+
+        let pending_transfer = schema.pending_transfer(&tx_hash).unwrap();
+        schema.fulfill_pending_transfer(pending_transfer);
+        assert!(schema.pending_transfer(&tx_hash).unwrap().fulfilled);
+        assert!(!schema.pending_transfer(&tx_hash).unwrap().cancelled);
+
+        let cancelled_hash = Hash::new([1; 32]);
+        schema.create_pending_transfer(cancelled_hash, &from, &to, &approver, native_asset_id(), 10, 1, 100);
+
+        let pending_transfer = schema.pending_transfer(&cancelled_hash).unwrap();
+        schema.cancel_pending_transfer(pending_transfer);
+        assert!(schema.pending_transfer(&cancelled_hash).unwrap().cancelled);
+        assert!(!schema.pending_transfer(&cancelled_hash).unwrap().fulfilled);
+    }
+
+    #[test]
+    fn pending_transfer_proof_covers_an_existing_transfer() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (from, _) = gen_keypair();
+        let (to, _) = gen_keypair();
+        let (approver, _) = gen_keypair();
+        let tx_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_pending_transfer(tx_hash, &from, &to, &approver, native_asset_id(), 10, 1, 100);
+
+        let proof = schema.pending_transfer_proof(&tx_hash);
+        let checked = proof.check().unwrap();
+        assert_eq!(
+            checked.entries().map(|(hash, _)| *hash).collect::<Vec<_>>(),
+            vec![tx_hash]
+        );
+    }
+}
+
+#[cfg(test)]
+mod state_hash {
+    use exonum::{
+        crypto::{gen_keypair, Hash},
+        storage::{Database, MemoryDB},
+    };
+    use exonum_cryptocurrency_advanced::{asset::native_asset_id, schema::Schema};
+
+    #[test]
+    fn state_hash_changes_when_a_pending_transfer_is_created() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (from, _) = gen_keypair();
+        let (to, _) = gen_keypair();
+        let (approver, _) = gen_keypair();
+        let tx_hash = Hash::default();
+
+        let empty_state_hash = Schema::new(&fork).state_hash();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_pending_transfer(tx_hash, &from, &to, &approver, native_asset_id(), 10, 1, 100);
+
+        assert_ne!(Schema::new(&fork).state_hash(), empty_state_hash);
+    }
+
+    #[test]
+    fn fee_collector_round_trips_through_the_entry() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (collector, _) = gen_keypair();
+
+        let mut schema = Schema::new(&mut fork);
+        assert_eq!(schema.fee_collector(), None);
+
+        schema.set_fee_collector(collector);
+        assert_eq!(schema.fee_collector(), Some(collector));
+    }
+
+    #[test]
+    fn wallet_history_proof_covers_the_wallet_creation_record() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (pub_key, _) = gen_keypair();
+        let tx_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_wallet(&pub_key, "Alice", &tx_hash);
+
+        let proof = schema.wallet_history_proof(&pub_key, 0, 1);
+        let checked = proof.check().unwrap();
+        assert_eq!(checked.merkle_root(), schema.wallet_history(&pub_key).merkle_root());
+    }
+}
+
+#[cfg(test)]
+mod pending_swaps {
+    use exonum::{
+        crypto::{gen_keypair, Hash},
+        storage::{Database, MemoryDB},
+    };
+    use exonum_cryptocurrency_advanced::{asset::native_asset_id, schema::Schema};
+
+    #[test]
+    fn create_pending_swap_stores_the_deadline_height() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (maker, _) = gen_keypair();
+        let (taker, _) = gen_keypair();
+        let offer_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_pending_swap(
+            offer_hash,
+            &maker,
+            &taker,
+            native_asset_id(),
+            10,
+            native_asset_id(),
+            5,
+            1500,
+        );
+
+        let swap = schema.pending_swap(&offer_hash).unwrap();
+        assert_eq!(swap.deadline_height, 1500);
+        assert!(!swap.fulfilled);
+        assert!(!swap.cancelled);
+    }
+
+    #[test]
+    fn fulfilling_a_pending_swap_does_not_mark_it_cancelled() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (maker, _) = gen_keypair();
+        let (taker, _) = gen_keypair();
+        let offer_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_pending_swap(offer_hash, &maker, &taker, native_asset_id(), 10, native_asset_id(), 5, 100);
+
+        let swap = schema.pending_swap(&offer_hash).unwrap();
+        schema.fulfill_pending_swap(swap);
+        assert!(schema.pending_swap(&offer_hash).unwrap().fulfilled);
+        assert!(!schema.pending_swap(&offer_hash).unwrap().cancelled);
+    }
+
+    #[test]
+    fn cancelling_a_pending_swap_does_not_mark_it_fulfilled() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (maker, _) = gen_keypair();
+        let (taker, _) = gen_keypair();
+        let offer_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_pending_swap(offer_hash, &maker, &taker, native_asset_id(), 10, native_asset_id(), 5, 100);
+
+        let swap = schema.pending_swap(&offer_hash).unwrap();
+        schema.cancel_pending_swap(swap);
+        assert!(schema.pending_swap(&offer_hash).unwrap().cancelled);
+        assert!(!schema.pending_swap(&offer_hash).unwrap().fulfilled);
+    }
+
+    #[test]
+    fn pending_swap_proof_covers_an_existing_offer() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (maker, _) = gen_keypair();
+        let (taker, _) = gen_keypair();
+        let offer_hash = Hash::default();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_pending_swap(offer_hash, &maker, &taker, native_asset_id(), 10, native_asset_id(), 5, 100);
+
+        let proof = schema.pending_swap_proof(&offer_hash);
+        let checked = proof.check().unwrap();
+        assert_eq!(
+            checked.entries().map(|(hash, _)| *hash).collect::<Vec<_>>(),
+            vec![offer_hash]
+        );
+    }
+
+    #[test]
+    fn state_hash_changes_when_a_pending_swap_is_created() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (maker, _) = gen_keypair();
+        let (taker, _) = gen_keypair();
+        let offer_hash = Hash::default();
+
+        let empty_state_hash = Schema::new(&fork).state_hash();
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_pending_swap(offer_hash, &maker, &taker, native_asset_id(), 10, native_asset_id(), 5, 100);
+
+        assert_ne!(Schema::new(&fork).state_hash(), empty_state_hash);
+    }
+}
+
+#[cfg(test)]
+mod swap_execution {
+    use exonum::{
+        blockchain::{Service, Transaction},
+        crypto::{gen_keypair, Hash},
+        messages::{Message, RawTransaction, Signed},
+        storage::Snapshot,
+    };
+    use exonum_cryptocurrency_advanced::{
+        asset::native_asset_id,
+        schema::Schema,
+        transactions::{AcceptSwap, CreateWallet, SwapOffer, WalletTransactions},
+        CRYPTOCURRENCY_SERVICE_ID,
+    };
+    use exonum_testkit::TestKitBuilder;
+
+    struct CurrencyService;
+
+    impl Service for CurrencyService {
+        fn service_id(&self) -> u16 {
+            CRYPTOCURRENCY_SERVICE_ID
+        }
+
+        fn service_name(&self) -> &str {
+            "cryptocurrency"
+        }
+
+        fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+            Schema::new(snapshot).state_hash()
+        }
+
+        fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+            let tx: WalletTransactions = WalletTransactions::tx_from_raw(raw)?;
+            Ok(tx.into())
+        }
+    }
+
+    // Drives `SwapOffer`/`AcceptSwap` through real `Transaction::execute`, rather than replaying
+    // schema mutations by hand -- this is the only way to catch a check that rejects every
+    // offer before any funds are ever frozen (see the balance check in `SwapOffer::execute`,
+    // which does not use `can_confirm_withdrawal`).
+    #[test]
+    fn accept_swap_succeeds_between_two_ordinary_wallets() {
+        let mut testkit = TestKitBuilder::validator()
+            .with_service(CurrencyService)
+            .create();
+        let (maker_pk, maker_sk) = gen_keypair();
+        let (taker_pk, taker_sk) = gen_keypair();
+
+        testkit.create_block_with_transactions(vec![
+            CreateWallet::sign("Maker", &maker_pk, &maker_sk),
+            CreateWallet::sign("Taker", &taker_pk, &taker_sk),
+        ]);
+
+        let asset_id = native_asset_id();
+        let offered_amount = 10;
+        let wanted_amount = 5;
+
+        let snapshot = testkit.snapshot();
+        let schema = Schema::new(&snapshot);
+        let maker_balance_before = schema.wallet(&maker_pk).unwrap().balance;
+        let taker_balance_before = schema.wallet(&taker_pk).unwrap().balance;
+        drop(schema);
+        drop(snapshot);
+
+        let offer = SwapOffer::sign(
+            &maker_pk,
+            asset_id,
+            offered_amount,
+            asset_id,
+            wanted_amount,
+            &taker_pk,
+            0,
+            &maker_sk,
+        );
+        let offer_hash = offer.hash();
+        testkit.create_block_with_transaction(offer);
+
+        testkit.create_block_with_transaction(AcceptSwap::sign(&taker_pk, &offer_hash, 0, &taker_sk));
+
+        let snapshot = testkit.snapshot();
+        let schema = Schema::new(&snapshot);
+        let maker = schema.wallet(&maker_pk).unwrap();
+        let taker = schema.wallet(&taker_pk).unwrap();
+
+        assert_eq!(
+            maker.balance,
+            maker_balance_before - offered_amount as i64 + wanted_amount as i64
+        );
+        assert_eq!(
+            taker.balance,
+            taker_balance_before - wanted_amount as i64 + offered_amount as i64
+        );
+        assert_eq!(maker.frozen_amount, 0);
+        assert!(schema.pending_swap(&offer_hash).unwrap().fulfilled);
+    }
+}
+
+#[cfg(test)]
+mod transfer_overflow {
+    use exonum::{
+        blockchain::{Service, Transaction},
+        crypto::{gen_keypair, Hash},
+        messages::{Message, RawTransaction},
+        storage::Snapshot,
+    };
+    use exonum_cryptocurrency_advanced::{
+        asset::native_asset_id,
+        schema::Schema,
+        transactions::{CreateWallet, Transfer, WalletTransactions},
+        CRYPTOCURRENCY_SERVICE_ID,
+    };
+    use exonum_testkit::TestKitBuilder;
+
+    struct CurrencyService;
+
+    impl Service for CurrencyService {
+        fn service_id(&self) -> u16 {
+            CRYPTOCURRENCY_SERVICE_ID
+        }
+
+        fn service_name(&self) -> &str {
+            "cryptocurrency"
+        }
+
+        fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+            Schema::new(snapshot).state_hash()
+        }
+
+        fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+            let tx: WalletTransactions = WalletTransactions::tx_from_raw(raw)?;
+            Ok(tx.into())
+        }
+    }
+
+    // Regression test for the `checked_add` fix in `Transfer::execute`: an amount and fee that
+    // overflow a u64 used to wrap, which could freeze far less than was actually withdrawn.
+    #[test]
+    fn transfer_is_rejected_when_amount_and_fee_overflow_u64() {
+        let mut testkit = TestKitBuilder::validator()
+            .with_service(CurrencyService)
+            .create();
+
+        let (from_pk, from_sk) = gen_keypair();
+        let (to_pk, to_sk) = gen_keypair();
+        let (approver_pk, approver_sk) = gen_keypair();
+
+        testkit.create_block_with_transactions(vec![
+            CreateWallet::sign("Sender", &from_pk, &from_sk),
+            CreateWallet::sign("Receiver", &to_pk, &to_sk),
+            CreateWallet::sign("Approver", &approver_pk, &approver_sk),
+        ]);
+
+        let snapshot = testkit.snapshot();
+        let balance_before = Schema::new(&snapshot).wallet(&from_pk).unwrap().balance;
+        drop(snapshot);
+
+        let transfer = Transfer::sign(
+            &from_pk,
+            &to_pk,
+            &approver_pk,
+            native_asset_id(),
+            u64::max_value(),
+            1,
+            0,
+            &from_sk,
+        );
+        let tx_hash = transfer.hash();
+        testkit.create_block_with_transaction(transfer);
+
+        let snapshot = testkit.snapshot();
+        let schema = Schema::new(&snapshot);
+        let sender = schema.wallet(&from_pk).unwrap();
+
+        // Overflow must be rejected before any balance is touched or a pending transfer recorded.
+        assert_eq!(sender.balance, balance_before);
+        assert_eq!(sender.frozen_amount, 0);
+        assert!(schema.pending_transfer(&tx_hash).is_none());
+    }
+}
+
+#[cfg(test)]
+mod accept_swap_native_asset {
+    use exonum::{
+        crypto::{gen_keypair, Hash},
+        storage::{Database, MemoryDB},
+    };
+    use exonum_cryptocurrency_advanced::{asset::native_asset_id, schema::Schema};
+
+    // Regression test for a swap where both legs use the native asset: releasing the maker's
+    // frozen offer must not clobber the balance credit the maker just received from the taker,
+    // which happens if the release uses a wallet snapshot captured before that credit.
+    #[test]
+    fn releasing_the_frozen_offer_preserves_the_balance_credited_from_the_taker() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let (maker, _) = gen_keypair();
+        let (taker, _) = gen_keypair();
+        let tx_hash = Hash::default();
+        let asset_id = native_asset_id();
+        let offered_amount = 30;
+        let wanted_amount = 10;
+
+        let mut schema = Schema::new(&mut fork);
+        schema.create_wallet(&maker, "Maker", &tx_hash);
+        schema.create_wallet(&taker, "Taker", &tx_hash);
+        let initial_balance = schema.wallet(&maker).unwrap().balance;
+
+        // SwapOffer: freeze the maker's offered amount.
+        let maker_wallet = schema.wallet(&maker).unwrap();
+        schema.decrease_wallet_balance(maker_wallet, asset_id, offered_amount, &tx_hash);
+
+        // AcceptSwap: debit the taker and credit the maker with the wanted amount, then
+        // release the maker's frozen offer to the taker.
+        let taker_wallet = schema.wallet(&taker).unwrap();
+        schema.debit_wallet_balance(taker_wallet, asset_id, wanted_amount, &tx_hash);
+
+        let maker_wallet_after_credit = schema.wallet(&maker).unwrap();
+        schema.increase_wallet_balance(maker_wallet_after_credit, asset_id, wanted_amount, &tx_hash);
+
+        let maker_wallet_before_release = schema.wallet(&maker).unwrap();
+        schema.decrease_wallet_frozen_balance(maker_wallet_before_release, asset_id, offered_amount, &tx_hash);
+
+        let maker = schema.wallet(&maker).unwrap();
+        assert_eq!(maker.balance, initial_balance - offered_amount as i64 + wanted_amount as i64);
+        assert_eq!(maker.frozen_amount, 0);
     }
 }